@@ -1,11 +1,95 @@
-use std::cell::RefCell;
+use std::cell::Cell;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 
 #[allow(unused_imports)]
 use crate as dylib_hook;
 
 // Thread-local flag to track internal calls
 thread_local! {
-    static IN_HOOK: RefCell<bool> = RefCell::new(false);
+    static IN_HOOK: Cell<bool> = Cell::new(false);
+}
+
+/// Error returned when a hooked function's real implementation cannot be
+/// resolved, either because the target library could not be opened or
+/// because the symbol is missing from it.
+#[derive(Debug)]
+pub enum HookError {
+    /// `dlopen` of the configured target library failed; the string is the
+    /// `dlerror()` message, or a note that the path contains a NUL byte.
+    DlopenFailed(String),
+    /// `dlsym` could not find the symbol in `RTLD_NEXT` or the configured
+    /// target library; the string is the `dlerror()` message.
+    SymbolNotFound(String),
+}
+
+impl fmt::Display for HookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HookError::DlopenFailed(msg) => write!(f, "dlopen failed: {msg}"),
+            HookError::SymbolNotFound(msg) => write!(f, "dlsym failed: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for HookError {}
+
+/// Reads the current `dlerror()` message, if any.
+#[doc(hidden)]
+pub fn dlerror_message() -> String {
+    unsafe {
+        let msg = libc::dlerror();
+        if msg.is_null() {
+            "unknown error".to_string()
+        } else {
+            std::ffi::CStr::from_ptr(msg).to_string_lossy().into_owned()
+        }
+    }
+}
+
+// Per-thread xorshift64 state for fault-injection hooks, seeded from the
+// thread id so threads don't all draw the same sequence.
+thread_local! {
+    static FAULT_RNG: Cell<u64> = Cell::new(thread_seed());
+}
+
+fn thread_seed() -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    std::thread::current().id().hash(&mut hasher);
+    hasher.finish() | 1
+}
+
+/// Draws the next pseudo-random number for the current thread and reports
+/// whether it landed a 1-in-`odds` fault. `odds == 0` never faults (avoids
+/// dividing by zero); `odds == 1` always faults.
+pub fn should_fault(odds: u32) -> bool {
+    if odds == 0 {
+        return false;
+    }
+    if odds == 1 {
+        return true;
+    }
+    FAULT_RNG.with(|cell| {
+        let mut x = cell.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        cell.set(x);
+        x % odds as u64 == 0
+    })
+}
+
+/// Sets `errno` via `libc::__errno_location`, for hooks that emulate a
+/// failing libc call.
+pub fn set_errno(value: i32) {
+    unsafe {
+        *libc::__errno_location() = value;
+    }
+}
+
+/// Reads the current `errno` via `libc::__errno_location`.
+pub fn get_errno() -> i32 {
+    unsafe { *libc::__errno_location() }
 }
 
 pub fn with_hook_protection<F, G, R>(f: F, f2: G) -> R
@@ -14,13 +98,13 @@ where
     G: FnOnce() -> R,
 {
     IN_HOOK.with(|flag| {
-        if *flag.borrow() {
+        if flag.get() {
             // If already in a hook, bypass and execute the real function
             return f2();
         }
-        *flag.borrow_mut() = true; 
-        let result = f(); 
-        *flag.borrow_mut() = false;
+        flag.set(true);
+        let result = f();
+        flag.set(false);
         result
     })
 }
@@ -30,20 +114,20 @@ where
     F: FnOnce() -> R,
 {
     IN_HOOK.with(|flag| {
-        let was_in_hook = *flag.borrow();
-        *flag.borrow_mut() = true;
+        let was_in_hook = flag.get();
+        flag.set(true);
         let result = f();
-        *flag.borrow_mut() = was_in_hook;
+        flag.set(was_in_hook);
         result
     })
 }
 
 pub fn disable_hooks() {
-    IN_HOOK.with(|flag| *flag.borrow_mut() = true);
+    IN_HOOK.with(|flag| flag.set(true));
 }
 
 pub fn enable_hooks() {
-    IN_HOOK.with(|flag| *flag.borrow_mut() = false);
+    IN_HOOK.with(|flag| flag.set(false));
 }
 
 
@@ -59,6 +143,17 @@ macro_rules! create_hooks {
 #[macro_export]
 macro_rules! create_hook {
     ($orig_fn:ident ($($param:ident: $ptype:ty),*) -> $ret:ty) => {
+        $crate::__create_hook_impl!($orig_fn($($param: $ptype),*) -> $ret; None);
+    };
+    ($orig_fn:ident ($($param:ident: $ptype:ty),*) -> $ret:ty, from $lib:expr) => {
+        $crate::__create_hook_impl!($orig_fn($($param: $ptype),*) -> $ret; Some($lib));
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __create_hook_impl {
+    ($orig_fn:ident ($($param:ident: $ptype:ty),*) -> $ret:ty; $initial_target:expr) => {
         #[allow(dead_code)]
         #[unsafe(no_mangle)]
         pub unsafe extern "C" fn $orig_fn($($param: $ptype),*) -> $ret {
@@ -75,31 +170,78 @@ macro_rules! create_hook {
         #[allow(dead_code)]
         pub mod $orig_fn {
             use super::*;
-            use std::sync::{Mutex, atomic::AtomicPtr};
-            
+            use std::sync::{Arc, LazyLock, Mutex, atomic::{AtomicU64, Ordering}};
+            use arc_swap::ArcSwap;
+
+
+            /// Identifies a previously-added hook so it can be removed again.
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub struct HookId(u64);
+
+            static NEXT_HOOK_ID: AtomicU64 = AtomicU64::new(0);
+
+            type HookList = Vec<(HookId, i32, HookFn)>;
+
+            /// Snapshot of the currently active hooks, swapped in whole by
+            /// `add_hook`/`remove_hook` via `ArcSwap::rcu`, which serializes
+            /// concurrent writers with a compare-and-swap retry loop and
+            /// reclaims old snapshots once no reader can still be holding one.
+            /// Readers (`Chain::new`) take a single lock-free `load_full`,
+            /// with no locking and no unsound hand-rolled refcounting on the
+            /// hot call path.
+            static HOOKS: LazyLock<ArcSwap<HookList>> = LazyLock::new(|| {
+                ArcSwap::new(Arc::new(HookList::new()))
+            });
+
+            /// A hook stored in the chain. Boxed as a trait object (rather
+            /// than a bare `fn` pointer) so a hook can carry its own captured
+            /// state, e.g. `add_fault_hook`'s config.
+            type HookClosure = dyn Fn($($ptype),*, &mut Chain) -> $ret + Send + Sync;
 
-            pub static HOOKS: Mutex<Vec<HookFn>> = Mutex::new(vec![]);
             #[derive(Clone)]
             pub struct HookFn {
-                pub f: fn($($ptype),*, &mut Chain) -> $ret,
+                pub f: Arc<HookClosure>,
+            }
+
+            fn load_hooks() -> Arc<HookList> {
+                HOOKS.load_full()
+            }
+
+            /// Replaces the active hook list with `f`'s result. `f` may run
+            /// more than once if another writer races it; each retry sees the
+            /// hooks active just before that attempt's swap.
+            fn update_hooks(f: impl Fn(&HookList) -> HookList) {
+                HOOKS.rcu(|hooks| f(hooks));
             }
 
             pub struct Chain {
+                hooks: Arc<HookList>,
                 index: usize,
+                last_errno: Option<i32>,
             }
             impl Chain {
                 pub fn new() -> Self {
-                    Chain { index: 0 }
+                    Chain {
+                        hooks: load_hooks(),
+                        index: 0,
+                        last_errno: None,
+                    }
+                }
+
+                /// `errno` as left by the most recent `call_orig` reached
+                /// through this chain, or `None` if the real function hasn't
+                /// been called yet. Lets a post-hook see what the real
+                /// function set before deciding whether to override it.
+                pub fn last_errno(&self) -> Option<i32> {
+                    self.last_errno
                 }
+
                 pub fn call(&mut self, $($param: $ptype),*) -> $ret {
-                    let hook = {
-                        let hooks = HOOKS.lock().unwrap();
-                        hooks.get(self.index).cloned()
-                    };
+                    let hook = self.hooks.get(self.index).map(|(_, _, hook)| hook.f.clone());
                     match hook {
                         Some(hook) => {
                             self.index += 1;
-                            let result = (hook.f)($($param),*, self);
+                            let result = hook($($param),*, self);
                             result
                         }
                         None => {
@@ -108,32 +250,322 @@ macro_rules! create_hook {
                     }
                 }
             }
-            pub fn add_hook(hook: fn($($ptype),*, &mut Chain) -> $ret) {
-                let mut hooks = HOOKS.lock().unwrap();
-                hooks.push(HookFn { f: hook });
+
+            /// Adds `hook` with the default priority (`0`). Hooks with equal
+            /// priority run in the order they were added.
+            #[must_use = "dropping the HookId makes the hook impossible to remove"]
+            pub fn add_hook(hook: fn($($ptype),*, &mut Chain) -> $ret) -> HookId {
+                add_hook_with_priority(hook, 0)
+            }
+
+            /// Adds `hook`, ordering it among the other hooks by `priority`
+            /// (lower runs first). Hooks with equal priority run in the order
+            /// they were added.
+            #[must_use = "dropping the HookId makes the hook impossible to remove"]
+            pub fn add_hook_with_priority(hook: fn($($ptype),*, &mut Chain) -> $ret, priority: i32) -> HookId {
+                add_closure_hook_with_priority(hook, priority)
+            }
+
+            /// Like `add_hook_with_priority`, but accepts any closure (so a
+            /// hook can carry its own captured state, e.g. `add_fault_hook`'s
+            /// configuration) instead of only a bare function pointer.
+            fn add_closure_hook_with_priority(
+                hook: impl Fn($($ptype),*, &mut Chain) -> $ret + Send + Sync + 'static,
+                priority: i32,
+            ) -> HookId {
+                let id = HookId(NEXT_HOOK_ID.fetch_add(1, Ordering::SeqCst));
+                let hook: Arc<HookClosure> = Arc::new(hook);
+                update_hooks(|hooks| {
+                    let mut hooks = hooks.clone();
+                    let pos = hooks.iter().position(|(_, p, _)| *p > priority).unwrap_or(hooks.len());
+                    hooks.insert(pos, (id, priority, HookFn { f: hook.clone() }));
+                    hooks
+                });
+                id
+            }
+
+            /// Removes a hook previously added with `add_hook`/`add_hook_with_priority`.
+            /// A no-op if `id` has already been removed.
+            pub fn remove_hook(id: HookId) {
+                update_hooks(|hooks| {
+                    hooks.iter().filter(|(hid, _, _)| *hid != id).cloned().collect()
+                });
+            }
+
+            /// RAII handle that removes its hook on drop, for temporary or
+            /// scoped interposition (e.g. for the duration of one test).
+            pub struct HookGuard {
+                id: HookId,
+            }
+            impl HookGuard {
+                pub fn new(id: HookId) -> Self {
+                    HookGuard { id }
+                }
+            }
+            impl Drop for HookGuard {
+                fn drop(&mut self) {
+                    remove_hook(self.id);
+                }
             }
 
-            pub fn chain_orig($($param: $ptype),*, _: &mut Chain) -> $ret {
-                call_orig($($param),*)
+            pub fn chain_orig($($param: $ptype),*, chain: &mut Chain) -> $ret {
+                let result = call_orig($($param),*);
+                chain.last_errno = Some(dylib_hook::get_errno());
+                result
             }
 
-            pub fn call_orig($($param: $ptype),*) -> $ret {
-                use std::sync::LazyLock;
-
-                static REAL: LazyLock<AtomicPtr<libc::c_void>> = LazyLock::new(|| {
-                    AtomicPtr::new( unsafe {
-                            libc::dlsym(
-                                libc::RTLD_NEXT,
-                                concat!(stringify!($orig_fn), "\0").as_ptr() as *const c_char,
-                            )
+            /// Sets `errno`, for a hook that wants its early return to look
+            /// like a genuine libc failure.
+            pub fn set_errno(value: i32) {
+                dylib_hook::set_errno(value);
+            }
+
+            /// Sets `errno` to `value` and returns `ret` in one call.
+            pub fn with_errno(value: i32, ret: $ret) -> $ret {
+                set_errno(value);
+                ret
+            }
+
+            /// Views a `(ptr, len)` FFI argument pair (e.g. `read`'s `buf`/
+            /// `count`) as a byte slice. Returns `None` on a null `ptr`, and
+            /// clamps `len` to `isize::MAX` so a bogus count can't produce a
+            /// slice bigger than Rust slices are allowed to address.
+            ///
+            /// # Safety
+            /// `ptr` must be valid for reads and writes of `len` (after
+            /// clamping) bytes for the lifetime of the returned slice, per
+            /// `slice::from_raw_parts_mut`.
+            pub unsafe fn buf_as_slice<'a>(ptr: *mut std::ffi::c_void, len: usize) -> Option<&'a mut [u8]> {
+                if ptr.is_null() {
+                    return None;
+                }
+                let len = len.min(isize::MAX as usize);
+                Some(unsafe { std::slice::from_raw_parts_mut(ptr as *mut u8, len) })
+            }
+
+            /// Views a NUL-terminated C-string FFI argument (e.g. `open`'s
+            /// `cpath`) as a `CStr`. Returns `None` on a null `ptr`.
+            ///
+            /// # Safety
+            /// `ptr` must point to a valid, NUL-terminated C string for the
+            /// lifetime of the returned `CStr`, per `CStr::from_ptr`.
+            pub unsafe fn as_cstr<'a>(ptr: *const std::ffi::c_char) -> Option<&'a std::ffi::CStr> {
+                if ptr.is_null() {
+                    return None;
+                }
+                Some(unsafe { std::ffi::CStr::from_ptr(ptr) })
+            }
+
+            /// `error_value` is an FFI return type (often a raw pointer);
+            /// hooked calls already hand these across threads by design, so
+            /// assert thread safety here the same way the rest of this module
+            /// relies on it.
+            struct FaultValue($ret);
+            unsafe impl Send for FaultValue {}
+            unsafe impl Sync for FaultValue {}
+
+            /// Adds a fault-injection hook that, with probability `1/odds`,
+            /// short-circuits the chain and returns `error_value` instead of
+            /// calling through, optionally setting `errno` first so the
+            /// failure looks like a genuine libc error. `odds == 0` never
+            /// faults; `odds == 1` always faults.
+            ///
+            /// The config is captured by the hook closure itself and carried
+            /// in the hook-chain snapshot rather than a module-global, so
+            /// calling this more than once installs independent fault hooks
+            /// instead of clobbering a shared config (which would also
+            /// compound their probabilities), and reading it on the call path
+            /// never takes a lock.
+            #[must_use = "dropping the HookId makes the hook impossible to remove"]
+            pub fn add_fault_hook(odds: u32, error_value: $ret, errno: Option<i32>) -> HookId {
+                let error_value = FaultValue(error_value);
+                add_closure_hook_with_priority(
+                    move |$($param: $ptype),*, chain: &mut Chain| {
+                        // Forces the closure to capture the whole `FaultValue`
+                        // (so its `unsafe impl Send/Sync` applies) instead of
+                        // precise-capturing just the `.0` field inside it.
+                        let error_value = &error_value;
+                        if dylib_hook::should_fault(odds) {
+                            if let Some(errno) = errno {
+                                dylib_hook::set_errno(errno);
+                            }
+                            error_value.0.clone()
+                        } else {
+                            chain.call($($param),*)
                         }
-                    )
-                });
+                    },
+                    0,
+                )
+            }
+
+            /// Minimal, glibc-specific mirror of `struct link_map` (not exposed
+            /// by the `libc` crate), just enough to read `l_name` back from
+            /// `dlinfo(..., RTLD_DI_LINKMAP, ...)`.
+            #[repr(C)]
+            struct LinkMap {
+                l_addr: usize,
+                l_name: *mut libc::c_char,
+                l_ld: *mut libc::c_void,
+                l_next: *mut LinkMap,
+                l_prev: *mut LinkMap,
+            }
 
+            /// The library `call_orig`/`try_call_orig` resolve the real symbol
+            /// from, plus everything resolved lazily from it. Swapped in whole
+            /// by `set_target_library` so a concurrent reader never sees a
+            /// handle paired with another configuration's path or cached
+            /// symbol.
+            struct TargetConfig {
+                /// Path this config was configured with, or `None` to resolve
+                /// via `RTLD_NEXT` (the default).
+                path: Option<String>,
+                /// `dlopen` handle for `path`, resolved lazily on first use.
+                handle: Mutex<*mut libc::c_void>,
+                /// Path `dlinfo` reports the loader actually resolved `handle`
+                /// to, cached lazily on first use.
+                resolved_path: Mutex<Option<String>>,
+                /// Cached real-symbol pointer, resolved lazily on first use.
+                real: Mutex<Option<*mut libc::c_void>>,
+            }
+            // `handle`/`real` hold raw `dlopen`/`dlsym` pointers, which are
+            // just addresses into a library mapped for the process's
+            // lifetime and are fine to hand across threads like the rest of
+            // this module already does.
+            unsafe impl Send for TargetConfig {}
+            unsafe impl Sync for TargetConfig {}
+
+            static TARGET: LazyLock<ArcSwap<TargetConfig>> = LazyLock::new(|| {
+                ArcSwap::new(Arc::new(TargetConfig {
+                    path: $initial_target.map(|s: &str| s.to_string()),
+                    handle: Mutex::new(std::ptr::null_mut()),
+                    resolved_path: Mutex::new(None),
+                    real: Mutex::new(None),
+                }))
+            });
+
+            /// Returns the path of the library the loader actually resolved
+            /// `$orig_fn`'s real implementation from (per `dlinfo`), or `None`
+            /// when resolving via `RTLD_NEXT` or when the library cannot be
+            /// opened. Opens the configured library first if it hasn't been
+            /// opened yet.
+            pub fn target_library() -> Option<String> {
+                let config = TARGET.load_full();
+                let handle = ensure_handle(&config).ok()?;
+                if handle.is_null() {
+                    return None;
+                }
+                let mut cached = config.resolved_path.lock().unwrap();
+                if cached.is_none() {
+                    *cached = realized_path(handle).or_else(|| config.path.clone());
+                }
+                cached.clone()
+            }
+
+            /// Configures `call_orig`/`try_call_orig` to resolve the real symbol
+            /// from `path` (via `dlopen`) instead of `RTLD_NEXT`. The new handle,
+            /// path and symbol cache are swapped in as a single unit, so a
+            /// concurrent `try_call_orig` always sees either the old
+            /// configuration or the new one in full, never a mix of the two.
+            pub fn set_target_library(path: &str) -> Result<(), dylib_hook::HookError> {
+                let handle = dlopen_path(path)?;
+                TARGET.store(Arc::new(TargetConfig {
+                    path: Some(path.to_string()),
+                    handle: Mutex::new(handle),
+                    resolved_path: Mutex::new(None),
+                    real: Mutex::new(None),
+                }));
+                Ok(())
+            }
+
+            fn dlopen_path(path: &str) -> Result<*mut libc::c_void, dylib_hook::HookError> {
+                let cpath = std::ffi::CString::new(path)
+                    .map_err(|_| dylib_hook::HookError::DlopenFailed("path contains a NUL byte".to_string()))?;
+                let mut handle = unsafe { libc::dlopen(cpath.as_ptr(), libc::RTLD_NOW | libc::RTLD_NOLOAD) };
+                if handle.is_null() {
+                    handle = unsafe { libc::dlopen(cpath.as_ptr(), libc::RTLD_NOW) };
+                }
+                if handle.is_null() {
+                    Err(dylib_hook::HookError::DlopenFailed(dylib_hook::dlerror_message()))
+                } else {
+                    Ok(handle)
+                }
+            }
+
+            /// Asks the loader (via `dlinfo(RTLD_DI_LINKMAP)`) what path it
+            /// actually resolved `handle` to, which can differ from the path
+            /// passed to `dlopen` (e.g. a soname following symlinks).
+            fn realized_path(handle: *mut libc::c_void) -> Option<String> {
                 unsafe {
+                    let mut link_map: *mut LinkMap = std::ptr::null_mut();
+                    let rc = libc::dlinfo(
+                        handle,
+                        libc::RTLD_DI_LINKMAP,
+                        &mut link_map as *mut _ as *mut libc::c_void,
+                    );
+                    if rc != 0 || link_map.is_null() || (*link_map).l_name.is_null() {
+                        return None;
+                    }
+                    Some(std::ffi::CStr::from_ptr((*link_map).l_name).to_string_lossy().into_owned())
+                }
+            }
+
+            fn ensure_handle(config: &TargetConfig) -> Result<*mut libc::c_void, dylib_hook::HookError> {
+                let mut handle = config.handle.lock().unwrap();
+                if handle.is_null() {
+                    if let Some(path) = &config.path {
+                        *handle = dlopen_path(path)?;
+                    }
+                }
+                Ok(*handle)
+            }
+
+            fn resolve_real(config: &TargetConfig) -> Result<*mut libc::c_void, dylib_hook::HookError> {
+                let handle = ensure_handle(config)?;
+
+                let symbol = unsafe {
+                    if handle.is_null() {
+                        libc::dlsym(
+                            libc::RTLD_NEXT,
+                            concat!(stringify!($orig_fn), "\0").as_ptr() as *const c_char,
+                        )
+                    } else {
+                        libc::dlsym(
+                            handle,
+                            concat!(stringify!($orig_fn), "\0").as_ptr() as *const c_char,
+                        )
+                    }
+                };
+
+                if symbol.is_null() {
+                    Err(dylib_hook::HookError::SymbolNotFound(dylib_hook::dlerror_message()))
+                } else {
+                    Ok(symbol)
+                }
+            }
+
+            /// Calls the real `$orig_fn`, returning an error instead of crashing
+            /// when the symbol cannot be resolved.
+            pub fn try_call_orig($($param: $ptype),*) -> Result<$ret, dylib_hook::HookError> {
+                let config = TARGET.load_full();
+                let real = {
+                    let mut cached = config.real.lock().unwrap();
+                    if cached.is_none() {
+                        *cached = Some(resolve_real(&config)?);
+                    }
+                    cached.unwrap()
+                };
+
+                Ok(unsafe {
                     (::std::mem::transmute::<*const libc::c_void, unsafe extern "C" fn ( $($param: $ptype),* ) -> $ret>(
-                        REAL.load(std::sync::atomic::Ordering::SeqCst)
+                        real
                     ))($($param),*)
+                })
+            }
+
+            pub fn call_orig($($param: $ptype),*) -> $ret {
+                match try_call_orig($($param),*) {
+                    Ok(result) => result,
+                    Err(err) => panic!("dylib_hook: failed to resolve real `{}`: {err}", stringify!($orig_fn)),
                 }
             }
         }
@@ -144,6 +576,20 @@ mod tests {
     use super::*;
     use std::{ffi::{c_char, c_int}, cell::RefCell};
 
+    #[test]
+    fn should_fault_odds_zero_never_faults() {
+        for _ in 0..1000 {
+            assert!(!should_fault(0));
+        }
+    }
+
+    #[test]
+    fn should_fault_odds_one_always_faults() {
+        for _ in 0..1000 {
+            assert!(should_fault(1));
+        }
+    }
+
     #[test]
     fn single_hook() {
         create_hook!(open(cpath: *const c_char, oflag: c_int) -> c_int);
@@ -156,7 +602,7 @@ mod tests {
             let ret = chain.call(cpath, oflag);
             ret
         }
-        open::add_hook(hook_fn);
+        let _ = open::add_hook(hook_fn);
 
         let path = std::ffi::CString::new("/etc/passwd").unwrap();
         let fd = unsafe { open(path.as_ptr(), 0) };
@@ -182,8 +628,8 @@ mod tests {
             chain.call(cpath, mode)
         }
 
-        fopen::add_hook(hook1);
-        fopen::add_hook(hook2);
+        let _ = fopen::add_hook(hook1);
+        let _ = fopen::add_hook(hook2);
 
         let path = std::ffi::CString::new("/etc/passwd").unwrap();
         let mode = std::ffi::CString::new("r").unwrap();
@@ -212,8 +658,8 @@ mod tests {
             chain.call(dirfd, cpath, oflag)
         }
 
-        openat::add_hook(hook1);
-        openat::add_hook(hook2);
+        let _ = openat::add_hook(hook1);
+        let _ = openat::add_hook(hook2);
 
         let path = std::ffi::CString::new("/etc/passwd").unwrap();
         let fd = unsafe { openat(libc::AT_FDCWD, path.as_ptr(), 0) };
@@ -235,7 +681,7 @@ mod tests {
             -1
         }
 
-        open64::add_hook(hook_fn);
+        let _ = open64::add_hook(hook_fn);
 
         // Simulate an internal call using with_hook_protection
         let result = with_hook_protection(
@@ -251,6 +697,153 @@ mod tests {
         assert!(!HOOK_CALLED.with(|called| *called.borrow()));
     }
 
+    #[test]
+    fn priority_orders_hooks_and_ties_preserve_insertion_order() {
+        create_hook!(unlink(cpath: *const c_char) -> c_int);
+        thread_local! {
+            static ORDER: RefCell<Vec<&'static str>> = RefCell::new(Vec::new());
+        }
+
+        fn low(_cpath: *const c_char, chain: &mut unlink::Chain) -> c_int {
+            ORDER.with(|o| o.borrow_mut().push("low"));
+            chain.call(_cpath)
+        }
+        fn mid_first(_cpath: *const c_char, chain: &mut unlink::Chain) -> c_int {
+            ORDER.with(|o| o.borrow_mut().push("mid_first"));
+            chain.call(_cpath)
+        }
+        fn mid_second(_cpath: *const c_char, chain: &mut unlink::Chain) -> c_int {
+            ORDER.with(|o| o.borrow_mut().push("mid_second"));
+            chain.call(_cpath)
+        }
+        fn high(_cpath: *const c_char, _chain: &mut unlink::Chain) -> c_int {
+            ORDER.with(|o| o.borrow_mut().push("high"));
+            0
+        }
+
+        // Added out of priority order; equal-priority hooks must still run
+        // in the order they were added.
+        let _ = unlink::add_hook_with_priority(high, 10);
+        let _ = unlink::add_hook_with_priority(mid_first, 0);
+        let _ = unlink::add_hook_with_priority(low, -10);
+        let _ = unlink::add_hook_with_priority(mid_second, 0);
+
+        let path = std::ffi::CString::new("/nonexistent").unwrap();
+        unlink::call_orig(path.as_ptr());
+        let _ = unsafe { unlink(path.as_ptr()) };
+
+        ORDER.with(|o| {
+            assert_eq!(*o.borrow(), vec!["low", "mid_first", "mid_second", "high"]);
+        });
+    }
+
+    #[test]
+    fn remove_hook_stops_it_running() {
+        create_hook!(rmdir(cpath: *const c_char) -> c_int);
+        thread_local! {
+            static CALLED: RefCell<bool> = RefCell::new(false);
+        }
+
+        fn hook_fn(_cpath: *const c_char, chain: &mut rmdir::Chain) -> c_int {
+            CALLED.with(|c| *c.borrow_mut() = true);
+            chain.call(_cpath)
+        }
+
+        let id = rmdir::add_hook(hook_fn);
+        rmdir::remove_hook(id);
+
+        let path = std::ffi::CString::new("/nonexistent").unwrap();
+        let _ = unsafe { rmdir(path.as_ptr()) };
+        assert!(!CALLED.with(|c| *c.borrow()));
+    }
+
+    #[test]
+    fn hook_guard_removes_hook_on_drop() {
+        create_hook!(chdir(cpath: *const c_char) -> c_int);
+        thread_local! {
+            static CALLED: RefCell<bool> = RefCell::new(false);
+        }
+
+        fn hook_fn(_cpath: *const c_char, chain: &mut chdir::Chain) -> c_int {
+            CALLED.with(|c| *c.borrow_mut() = true);
+            chain.call(_cpath)
+        }
+
+        {
+            let _guard = chdir::HookGuard::new(chdir::add_hook(hook_fn));
+            let path = std::ffi::CString::new(".").unwrap();
+            let _ = unsafe { chdir(path.as_ptr()) };
+            assert!(CALLED.with(|c| *c.borrow()));
+        }
+        CALLED.with(|c| *c.borrow_mut() = false);
+
+        let path = std::ffi::CString::new(".").unwrap();
+        let _ = unsafe { chdir(path.as_ptr()) };
+        assert!(!CALLED.with(|c| *c.borrow()));
+    }
+
+    #[test]
+    fn buf_as_slice_rejects_null_and_clamps_len() {
+        create_hook!(read(fd: c_int, buf: *mut std::ffi::c_void, count: usize) -> isize);
+
+        assert!(unsafe { read::buf_as_slice(std::ptr::null_mut(), 8) }.is_none());
+
+        let mut byte = 0u8;
+        let ptr = &mut byte as *mut u8 as *mut std::ffi::c_void;
+        let huge = isize::MAX as usize + 1024;
+        let slice = unsafe { read::buf_as_slice(ptr, huge) }.unwrap();
+        assert_eq!(slice.len(), isize::MAX as usize);
+    }
+
+    #[test]
+    fn as_cstr_rejects_null() {
+        create_hook!(stat(cpath: *const c_char, buf: *mut libc::stat) -> c_int);
+
+        assert!(unsafe { stat::as_cstr(std::ptr::null()) }.is_none());
+
+        let path = std::ffi::CString::new("/etc/passwd").unwrap();
+        let cstr = unsafe { stat::as_cstr(path.as_ptr()) }.unwrap();
+        assert_eq!(cstr.to_str().unwrap(), "/etc/passwd");
+    }
+
+    #[test]
+    fn with_errno_sets_errno_and_returns_value() {
+        create_hook!(symlink(target: *const c_char, linkpath: *const c_char) -> c_int);
+
+        fn hook_fn(_target: *const c_char, _linkpath: *const c_char, _chain: &mut symlink::Chain) -> c_int {
+            symlink::with_errno(libc::EEXIST, -1)
+        }
+        let _ = symlink::add_hook(hook_fn);
+
+        let a = std::ffi::CString::new("a").unwrap();
+        let b = std::ffi::CString::new("b").unwrap();
+        let ret = unsafe { symlink(a.as_ptr(), b.as_ptr()) };
+        assert_eq!(ret, -1);
+        assert_eq!(get_errno(), libc::EEXIST);
+    }
+
+    #[test]
+    fn chain_last_errno_captures_call_orig_errno() {
+        create_hook!(mkdir(cpath: *const c_char, mode: libc::mode_t) -> c_int);
+        thread_local! {
+            static SEEN_ERRNO: RefCell<Option<i32>> = RefCell::new(None);
+        }
+
+        fn hook_fn(cpath: *const c_char, mode: libc::mode_t, chain: &mut mkdir::Chain) -> c_int {
+            let ret = chain.call(cpath, mode);
+            SEEN_ERRNO.with(|e| *e.borrow_mut() = chain.last_errno());
+            ret
+        }
+        let _ = mkdir::add_hook(hook_fn);
+
+        // A non-creatable path (no parent directory) fails with a real
+        // errno that call_orig/chain_orig should capture into the chain.
+        let path = std::ffi::CString::new("/no/such/parent/dir").unwrap();
+        let ret = unsafe { mkdir(path.as_ptr(), 0o755) };
+        assert_eq!(ret, -1);
+        assert_eq!(SEEN_ERRNO.with(|e| *e.borrow()), Some(libc::ENOENT));
+    }
+
     #[test]
     fn orig_bypasses_hooks() {
         create_hook!(fopen64(cpath: *const c_char, mode: *const c_char) -> *mut libc::FILE);
@@ -263,7 +856,7 @@ mod tests {
             chain.call(cpath, mode)
         }
 
-        fopen64::add_hook(hook_fn);
+        let _ = fopen64::add_hook(hook_fn);
 
         // Call the original function directly, bypassing hooks
         let path = std::ffi::CString::new("/etc/passwd").unwrap();
@@ -274,4 +867,31 @@ mod tests {
         // Ensure the hook was not called
         assert!(!HOOK_CALLED.with(|called| *called.borrow()));
     }
+
+    #[test]
+    fn try_call_orig_reports_missing_symbol() {
+        create_hook!(this_symbol_does_not_exist_anywhere(x: c_int) -> c_int);
+
+        match this_symbol_does_not_exist_anywhere::try_call_orig(0) {
+            Err(HookError::SymbolNotFound(_)) => {}
+            other => panic!("expected SymbolNotFound, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn set_target_library_resolves_named_library() {
+        create_hook!(access(cpath: *const c_char, mode: c_int) -> c_int, from "libc.so.6");
+
+        access::set_target_library("libc.so.6").unwrap();
+        let resolved = access::target_library().unwrap();
+        assert!(resolved.contains("libc"), "resolved={resolved}");
+
+        let path = std::ffi::CString::new("/etc/passwd").unwrap();
+        assert_eq!(access::call_orig(path.as_ptr(), 0), 0);
+
+        assert!(matches!(
+            access::set_target_library("definitely_not_a_real_lib.so"),
+            Err(HookError::DlopenFailed(_))
+        ));
+    }
 }